@@ -0,0 +1,75 @@
+// Safe command construction — resolve a program to an absolute path via a PATH
+// search before spawning it, so that an executable sitting in the repository's
+// working directory can never be picked up in place of the intended binary.
+// This mirrors starship's `create_command` and matters most on Windows, where
+// the current directory is part of the default executable search order.
+
+use std::ffi::OsStr;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Build a `Command` for `program`, resolving it to an absolute path through a
+/// PATH lookup first. On Unix the resolved path is the one the OS's own PATH
+/// search would pick, except that empty and relative PATH entries are skipped;
+/// on Windows the current working directory is deliberately
+/// excluded from the search so a malicious `git.exe`/`claude.cmd` cloned into a
+/// repo cannot hijack the spawn.
+pub fn create_command<T: AsRef<OsStr>>(program: T) -> io::Result<Command> {
+    Ok(Command::new(resolve_program(program.as_ref())?))
+}
+
+/// Resolve `program` to an absolute path through the same PATH search as
+/// [`create_command`], for callers that build a process some other way (e.g.
+/// the PTY shell, constructed via `portable_pty::CommandBuilder`).
+pub fn resolve_program<T: AsRef<OsStr>>(program: T) -> io::Result<PathBuf> {
+    let program = program.as_ref();
+    which(program).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find executable {:?} in PATH", program),
+        )
+    })
+}
+
+/// Resolve `program` to an absolute path by walking the directories in `PATH`.
+/// Returns `None` when the program cannot be located.
+fn which(program: &OsStr) -> Option<PathBuf> {
+    // An explicit path (containing a separator) is used verbatim.
+    let as_path = std::path::Path::new(program);
+    if as_path.components().count() > 1 {
+        return as_path.is_file().then(|| as_path.to_path_buf());
+    }
+
+    let paths = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&paths) {
+        // Never resolve against the current directory: on Windows that is what
+        // lets a repo-local executable shadow the real one. Empty entries and
+        // relative ones such as `.` both point into it.
+        if dir.as_os_str().is_empty() || dir.is_relative() {
+            continue;
+        }
+
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+
+        // On Windows the program name usually omits its extension; try each
+        // entry from PATHEXT (falling back to the common defaults).
+        #[cfg(windows)]
+        {
+            let pathext = std::env::var("PATHEXT")
+                .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+            for ext in pathext.split(';').filter(|e| !e.is_empty()) {
+                let ext = ext.trim_start_matches('.');
+                let candidate = dir.join(program).with_extension(ext);
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,75 @@
+// Application error type — a serializable, classified error that replaces the
+// stringly-typed `Result<_, String>` returned from Tauri commands. The `class`
+// field lets the frontend branch on the kind of failure (e.g. distinguish a
+// missing repository from a missing upstream) instead of parsing a message.
+
+use std::fmt;
+use std::str::Utf8Error;
+use std::sync::PoisonError;
+
+use serde::Serialize;
+
+/// Broad category of an [`AppError`], surfaced to the frontend so it can react
+/// differently to, say, a lock poisoning versus a git failure.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorClass {
+    Io,
+    Git,
+    Utf8,
+    Command,
+    Lock,
+    Generic,
+}
+
+/// A classified error carrying a human-readable message.
+#[derive(Serialize, Debug)]
+pub struct AppError {
+    pub class: ErrorClass,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn new(class: ErrorClass, message: impl Into<String>) -> Self {
+        AppError {
+            class,
+            message: message.into(),
+        }
+    }
+
+    /// A git failure carrying the command's stderr (or exit description).
+    pub fn git(message: impl Into<String>) -> Self {
+        AppError::new(ErrorClass::Git, message)
+    }
+
+    /// A failure spawning or locating an external command.
+    pub fn command(message: impl Into<String>) -> Self {
+        AppError::new(ErrorClass::Command, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.class, self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::new(ErrorClass::Io, e.to_string())
+    }
+}
+
+impl From<Utf8Error> for AppError {
+    fn from(e: Utf8Error) -> Self {
+        AppError::new(ErrorClass::Utf8, e.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for AppError {
+    fn from(e: PoisonError<T>) -> Self {
+        AppError::new(ErrorClass::Lock, e.to_string())
+    }
+}
@@ -2,8 +2,9 @@
 // Provides branch name, dirty state, ahead/behind counts, last commit info,
 // and a list of changed files via `git status --porcelain`.
 
+use crate::command::create_command;
+use crate::error::AppError;
 use serde::Serialize;
-use std::process::Command;
 
 #[derive(Serialize)]
 pub struct GitInfo {
@@ -11,6 +12,17 @@ pub struct GitInfo {
     pub dirty: bool,
     pub ahead: u32,
     pub behind: u32,
+    /// True when the branch is both ahead of and behind its upstream.
+    pub diverged: bool,
+    /// Per-category working-tree counts, classified from the porcelain XY codes.
+    pub conflicted: u32,
+    pub staged: u32,
+    pub modified: u32,
+    pub deleted: u32,
+    pub renamed: u32,
+    pub untracked: u32,
+    /// Number of entries in the stash.
+    pub stashed: u32,
     pub last_commit_hash: String,
     pub last_commit_message: String,
 }
@@ -22,25 +34,55 @@ pub struct ChangedFile {
 }
 
 /// Run a git command in the given repo and return stdout as a trimmed string.
-fn git_cmd(repo_path: &str, args: &[&str]) -> Result<String, String> {
-    let output = Command::new("git")
+///
+/// Returns a `Git`-class [`AppError`] carrying stderr when git exits non-zero,
+/// so callers can distinguish "not a repo" from "no upstream configured" from
+/// "branch has no commits yet" instead of silently collapsing to an empty
+/// string.
+fn git_cmd(repo_path: &str, args: &[&str]) -> Result<String, AppError> {
+    let output = create_command("git")
+        .map_err(|e| AppError::command(e.to_string()))?
         .args(["-C", repo_path])
         .args(args)
-        .output()
-        .map_err(|e| e.to_string())?;
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(AppError::git(if stderr.is_empty() {
+            format!("git {} failed", args.join(" "))
+        } else {
+            stderr
+        }));
+    }
+
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 /// Return high-level git status for the repo: current branch, dirty flag,
 /// ahead/behind counts relative to upstream, and the latest commit.
 #[tauri::command]
-pub fn git_info(repo_path: String) -> Result<GitInfo, String> {
-    let branch = git_cmd(&repo_path, &["rev-parse", "--abbrev-ref", "HEAD"])
-        .unwrap_or_default();
+pub fn git_info(repo_path: String) -> Result<GitInfo, AppError> {
+    // A failure here means the path isn't a git repository, or its branch has
+    // no commits yet — propagate it.
+    let branch = git_cmd(&repo_path, &["rev-parse", "--abbrev-ref", "HEAD"])?;
 
-    let porcelain = git_cmd(&repo_path, &["status", "--porcelain"])
-        .unwrap_or_default();
+    // `git_cmd` trims its output, which would strip the blank index column of
+    // the first entry (" M a.rs" → "M a.rs"). `--branch` puts a "## ..." header
+    // line first so every entry keeps both status columns; the header is
+    // dropped here.
+    let porcelain = git_cmd(&repo_path, &["status", "--porcelain", "--branch"])?
+        .lines()
+        .filter(|l| !l.starts_with("## "))
+        .collect::<Vec<_>>()
+        .join("\n");
     let dirty = !porcelain.is_empty();
+    let counts = GitStatusCounts::from_porcelain(&porcelain);
+
+    // A single stash-list call is cheaper than walking the reflog and behaves
+    // the same in repos that have never stashed (empty output → zero).
+    let stashed = git_cmd(&repo_path, &["stash", "list"])
+        .map(|s| s.lines().filter(|l| !l.is_empty()).count() as u32)
+        .unwrap_or(0);
 
     let (ahead, behind) = git_cmd(
         &repo_path,
@@ -71,11 +113,71 @@ pub fn git_info(repo_path: String) -> Result<GitInfo, String> {
         dirty,
         ahead,
         behind,
+        diverged: ahead > 0 && behind > 0,
+        conflicted: counts.conflicted,
+        staged: counts.staged,
+        modified: counts.modified,
+        deleted: counts.deleted,
+        renamed: counts.renamed,
+        untracked: counts.untracked,
+        stashed,
         last_commit_hash,
         last_commit_message,
     })
 }
 
+/// Working-tree status tallies derived by classifying each porcelain line's
+/// two-character `XY` code (index column `X`, worktree column `Y`).
+#[derive(Default)]
+struct GitStatusCounts {
+    conflicted: u32,
+    staged: u32,
+    modified: u32,
+    deleted: u32,
+    renamed: u32,
+    untracked: u32,
+}
+
+impl GitStatusCounts {
+    fn from_porcelain(porcelain: &str) -> Self {
+        let mut counts = GitStatusCounts::default();
+
+        for line in porcelain.lines().filter(|l| l.len() >= 2) {
+            let mut chars = line.chars();
+            let x = chars.next().unwrap_or(' ');
+            let y = chars.next().unwrap_or(' ');
+
+            // Unmerged entries: any of DD, AU, UD, UA, DU, AA, UU.
+            if x == 'U' || y == 'U' || (x == 'A' && y == 'A') || (x == 'D' && y == 'D') {
+                counts.conflicted += 1;
+                continue;
+            }
+
+            if x == '?' && y == '?' {
+                counts.untracked += 1;
+                continue;
+            }
+
+            // Index column (non-space, non-`?`) means the change is staged.
+            if x != ' ' {
+                counts.staged += 1;
+            }
+            if x == 'R' || y == 'R' {
+                counts.renamed += 1;
+            }
+
+            // Worktree column reflects unstaged modifications/deletions.
+            match y {
+                'M' => counts.modified += 1,
+                'D' => counts.deleted += 1,
+                _ => {}
+            }
+        }
+
+        counts
+    }
+}
+
 #[derive(Serialize)]
 pub struct FileDiff {
     pub old_content: String,
@@ -85,7 +187,7 @@ pub struct FileDiff {
 /// Return the old (HEAD) and new (working tree) content of a file for
 /// side-by-side diff rendering.
 #[tauri::command]
-pub fn git_file_diff(repo_path: String, file_path: String) -> Result<FileDiff, String> {
+pub fn git_file_diff(repo_path: String, file_path: String) -> Result<FileDiff, AppError> {
     // Try to get the committed version from HEAD
     let old_content = git_cmd(&repo_path, &["show", &format!("HEAD:{}", file_path)])
         .unwrap_or_default();
@@ -100,12 +202,241 @@ pub fn git_file_diff(repo_path: String, file_path: String) -> Result<FileDiff, S
     })
 }
 
+/// The kind of a line within a diff hunk.
+#[derive(Serialize, Clone, Copy, PartialEq)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+}
+
+/// A contiguous region of change with the surrounding context lines, ready for
+/// the frontend to render as gutter markers.
+#[derive(Serialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Return the diff between the HEAD version of a file and the working tree as a
+/// list of hunks. Each hunk carries its line ranges plus the typed lines so the
+/// frontend can render gutter markers and, later, stage individual hunks.
+///
+/// Files absent from HEAD are treated as empty (a single all-added hunk), and a
+/// trailing newline is ignored when comparing lines so a file that gains or
+/// loses its final newline doesn't report its last line as changed.
+#[tauri::command]
+pub fn git_file_hunks(repo_path: String, file_path: String) -> Result<Vec<DiffHunk>, AppError> {
+    // Read the HEAD version directly rather than through `git_cmd`, whose
+    // trimming would drop the first line's indentation and trailing blank lines
+    // and make unchanged lines show up as edits.
+    let spec = format!("HEAD:{}", file_path);
+    let old_content = create_command("git")
+        .ok()
+        .and_then(|mut cmd| {
+            cmd.args(["-C", repo_path.as_str(), "show", spec.as_str()])
+                .output()
+                .ok()
+        })
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default();
+
+    let full_path = std::path::Path::new(&repo_path).join(&file_path);
+    let new_content = std::fs::read_to_string(&full_path).unwrap_or_default();
+
+    Ok(diff_hunks(&old_content, &new_content, 3))
+}
+
+/// A single edit produced by backtracking the LCS table.
+enum Edit {
+    Keep(usize, usize),
+    Remove(usize),
+    Insert(usize),
+}
+
+/// Split a file into its lines for comparison, dropping a single trailing
+/// newline so a line is equal to its counterpart regardless of whether the file
+/// ends in a newline. An empty file yields no lines.
+fn split_lines(content: &str) -> Vec<&str> {
+    if content.is_empty() {
+        return Vec::new();
+    }
+    content
+        .strip_suffix('\n')
+        .unwrap_or(content)
+        .split('\n')
+        .collect()
+}
+
+/// Compute the line-level diff between two file versions and coalesce the edit
+/// script into hunks with `context` surrounding context lines.
+fn diff_hunks(old: &str, new: &str, context: usize) -> Vec<DiffHunk> {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    // Standard O(n·m) longest-common-subsequence dynamic-programming table.
+    let n = old_lines.len();
+    let m = new_lines.len();
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Backtrack into a linear edit script.
+    let mut edits: Vec<Edit> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_lines[i] == new_lines[j] {
+            edits.push(Edit::Keep(i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            edits.push(Edit::Remove(i));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(j));
+            j += 1;
+        }
+    }
+    while i < n {
+        edits.push(Edit::Remove(i));
+        i += 1;
+    }
+    while j < m {
+        edits.push(Edit::Insert(j));
+        j += 1;
+    }
+
+    // Group edits into hunks: a run of changes plus up to `context` context
+    // lines on either side, merging runs that are within 2·context of each other.
+    let changed: Vec<bool> = edits
+        .iter()
+        .map(|e| !matches!(e, Edit::Keep(_, _)))
+        .collect();
+
+    // The 1-based old/new line number at which each edit begins.
+    let mut positions: Vec<(usize, usize)> = Vec::with_capacity(edits.len());
+    let (mut oline, mut nline) = (1usize, 1usize);
+    for edit in &edits {
+        positions.push((oline, nline));
+        match edit {
+            Edit::Keep(_, _) => {
+                oline += 1;
+                nline += 1;
+            }
+            Edit::Remove(_) => oline += 1,
+            Edit::Insert(_) => nline += 1,
+        }
+    }
+
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut idx = 0;
+    let mut prev_end = 0;
+    while idx < edits.len() {
+        if !changed[idx] {
+            idx += 1;
+            continue;
+        }
+
+        // Leading context, clamped so it never reaches back into the previous
+        // hunk (which would duplicate those lines across both hunks).
+        let start = idx.saturating_sub(context).max(prev_end);
+
+        // Extend the hunk forward across nearby changes.
+        let mut end = idx;
+        loop {
+            // Advance past the current change run.
+            while end < edits.len() && changed[end] {
+                end += 1;
+            }
+            // Look ahead: fold a following run into this hunk when the gap of
+            // unchanged lines is at most 2·context — the standard unified-diff
+            // rule, since otherwise this hunk's trailing context and the next
+            // hunk's leading context would overlap.
+            let mut lookahead = end;
+            while lookahead < edits.len()
+                && lookahead < end + 2 * context
+                && !changed[lookahead]
+            {
+                lookahead += 1;
+            }
+            if lookahead < edits.len() && changed[lookahead] {
+                end = lookahead;
+            } else {
+                break;
+            }
+        }
+        // Trailing context.
+        end = (end + context).min(edits.len());
+
+        let mut lines = Vec::new();
+        let (old_start, new_start) = positions[start];
+        let mut old_count = 0usize;
+        let mut new_count = 0usize;
+
+        for edit in &edits[start..end] {
+            match edit {
+                Edit::Keep(oi, _) => {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::Context,
+                        content: old_lines[*oi].to_string(),
+                    });
+                    old_count += 1;
+                    new_count += 1;
+                }
+                Edit::Remove(oi) => {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::Removed,
+                        content: old_lines[*oi].to_string(),
+                    });
+                    old_count += 1;
+                }
+                Edit::Insert(nj) => {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::Added,
+                        content: new_lines[*nj].to_string(),
+                    });
+                    new_count += 1;
+                }
+            }
+        }
+
+        hunks.push(DiffHunk {
+            old_start,
+            old_lines: old_count,
+            new_start,
+            new_lines: new_count,
+            lines,
+        });
+
+        prev_end = end;
+        idx = end;
+    }
+
+    hunks
+}
+
 /// Return the list of changed files (staged + unstaged) in the working tree,
 /// each with a two-character git status code and its file path.
 #[tauri::command]
-pub fn git_changed_files(repo_path: String) -> Result<Vec<ChangedFile>, String> {
-    let porcelain = git_cmd(&repo_path, &["status", "--porcelain"])
-        .unwrap_or_default();
+pub fn git_changed_files(repo_path: String) -> Result<Vec<ChangedFile>, AppError> {
+    let porcelain = git_cmd(&repo_path, &["status", "--porcelain"])?;
 
     let files: Vec<ChangedFile> = porcelain
         .lines()
@@ -129,3 +460,123 @@ pub fn git_changed_files(repo_path: String) -> Result<Vec<ChangedFile>, String>
 
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(porcelain: &str) -> GitStatusCounts {
+        GitStatusCounts::from_porcelain(porcelain)
+    }
+
+    #[test]
+    fn from_porcelain_classifies_conflicts() {
+        for code in ["UU", "AA", "DD", "AU", "UD", "DU", "UA"] {
+            let c = counts(&format!("{} both.rs", code));
+            assert_eq!(c.conflicted, 1, "{code} should be conflicted");
+            assert_eq!(c.staged, 0);
+            assert_eq!(c.modified, 0);
+        }
+    }
+
+    #[test]
+    fn from_porcelain_classifies_untracked() {
+        let c = counts("?? new.rs");
+        assert_eq!(c.untracked, 1);
+        assert_eq!(c.staged, 0);
+    }
+
+    #[test]
+    fn from_porcelain_classifies_staged_modified_deleted_renamed() {
+        // Staged-only modification.
+        let c = counts("M  a.rs");
+        assert_eq!(c.staged, 1);
+        assert_eq!(c.modified, 0);
+
+        // Staged and unstaged modification of the same file.
+        let c = counts("MM a.rs");
+        assert_eq!(c.staged, 1);
+        assert_eq!(c.modified, 1);
+
+        // Unstaged modification only.
+        let c = counts(" M a.rs");
+        assert_eq!(c.staged, 0);
+        assert_eq!(c.modified, 1);
+
+        // Unstaged deletion.
+        let c = counts(" D a.rs");
+        assert_eq!(c.deleted, 1);
+
+        // Staged rename.
+        let c = counts("R  old.rs -> new.rs");
+        assert_eq!(c.renamed, 1);
+        assert_eq!(c.staged, 1);
+    }
+
+    #[test]
+    fn from_porcelain_tallies_multiple_lines() {
+        let c = counts("?? a.rs\n M b.rs\nM  c.rs\nUU d.rs");
+        assert_eq!(c.untracked, 1);
+        assert_eq!(c.modified, 1);
+        assert_eq!(c.staged, 1);
+        assert_eq!(c.conflicted, 1);
+    }
+}
+
+
+
+#[cfg(test)]
+mod diff_hunks_tests {
+    use super::*;
+
+    #[test]
+    fn diff_hunks_no_change() {
+        let hunks = diff_hunks("a\nb\nc\n", "a\nb\nc\n", 3);
+        assert!(hunks.is_empty());
+    }
+
+    #[test]
+    fn diff_hunks_all_added_when_absent_from_head() {
+        let hunks = diff_hunks("", "a\nb\n", 3);
+        assert_eq!(hunks.len(), 1);
+        let h = &hunks[0];
+        assert_eq!(h.old_lines, 0);
+        assert_eq!(h.new_lines, 2);
+        assert_eq!(h.new_start, 1);
+        assert!(h.lines.iter().all(|l| l.kind == DiffLineKind::Added));
+    }
+
+    #[test]
+    fn diff_hunks_without_trailing_newline() {
+        // Neither side ends in a newline; the appended line is a single add.
+        let hunks = diff_hunks("a", "a\nb", 3);
+        assert_eq!(hunks.len(), 1);
+        let added: Vec<&str> = hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.kind == DiffLineKind::Added)
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(added, vec!["b"]);
+    }
+
+    #[test]
+    fn diff_hunks_merges_runs_within_2_context() {
+        // Edits on lines 2 and 7 with 4 unchanged lines between them (≤ 2·3).
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nB\nc\nd\ne\nf\nG\nh\n";
+        let hunks = diff_hunks(old, new, 3);
+        assert_eq!(hunks.len(), 1);
+    }
+
+    #[test]
+    fn diff_hunks_splits_runs_beyond_2_context() {
+        // Edits on the first and last of 20 lines — far beyond 2·3 apart.
+        let old: String = (1..=20).map(|i| format!("l{i}\n")).collect();
+        let new = old
+            .replacen("l1\n", "L1\n", 1)
+            .replacen("l20\n", "L20\n", 1);
+        let hunks = diff_hunks(&old, &new, 3);
+        assert_eq!(hunks.len(), 2);
+    }
+}
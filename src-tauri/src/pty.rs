@@ -1,33 +1,57 @@
-// PTY commands — manage a pseudo-terminal session for the integrated terminal.
-// Creates a PTY running the user's shell, auto-launches `claude` inside it,
-// and streams output back to the frontend via Tauri events. Also supports
-// writing input and resizing the terminal.
+// PTY commands — manage pseudo-terminal sessions for the integrated terminal.
+// Each session runs the user's shell, auto-launches a command
+// (defaulting to `claude`), and streams its output back to the frontend via a
+// per-session `pty-output` event. Sessions are keyed by a frontend-supplied id
+// so multiple terminals (split panes, tabs) can coexist.
 
-use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
 use tauri::{AppHandle, Emitter, State};
 
-pub struct PtyState {
-    pub master: Option<Box<dyn MasterPty + Send>>,
+use crate::command::resolve_program;
+use crate::error::AppError;
+
+/// A single live terminal: its master PTY, the writer used for input, and the
+/// handle of the thread draining its output.
+pub struct PtySession {
+    pub master: Box<dyn MasterPty + Send>,
+    pub writer: Box<dyn Write + Send>,
+    pub child: Box<dyn Child + Send + Sync>,
+    pub reader_thread: Option<JoinHandle<()>>,
+}
+
+/// Holds every live [`PtySession`] keyed by its frontend-supplied session id.
+pub struct PtyManager {
+    pub sessions: HashMap<String, PtySession>,
 }
 
-pub struct WriterState {
-    pub writer: Option<Box<dyn Write + Send>>,
+/// Payload streamed on the `pty-output` event, tagged with the originating
+/// session so the frontend can route bytes to the correct xterm instance.
+#[derive(Serialize, Clone)]
+struct PtyOutput {
+    session_id: String,
+    data: String,
 }
 
-/// Spawn a new PTY shell session in the given working directory.
-/// Inherits essential environment variables (SHELL, HOME, USER, PATH),
-/// auto-launches `claude`, and begins streaming output via the "pty-output" event.
+/// Spawn a new PTY shell session under `session_id` in the given working
+/// directory. Inherits essential environment variables (SHELL, HOME, USER,
+/// PATH), auto-launches `command` (defaulting to `claude`), and begins
+/// streaming output via the per-session "pty-output" event.
 #[tauri::command]
 pub fn pty_create(
     app: AppHandle,
-    pty_state: State<'_, Mutex<PtyState>>,
-    writer_state: State<'_, Mutex<WriterState>>,
+    manager: State<'_, Mutex<PtyManager>>,
+    session_id: String,
     rows: u16,
     cols: u16,
     cwd: String,
-) -> Result<(), String> {
+    command: Option<String>,
+) -> Result<(), AppError> {
     let pty_system = native_pty_system();
 
     let pty_pair = pty_system
@@ -37,9 +61,12 @@ pub fn pty_create(
             pixel_width: 0,
             pixel_height: 0,
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppError::command(e.to_string()))?;
 
     let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    // Resolve the shell to an absolute path so a repo-local executable of the
+    // same name can't be launched in its place (see `create_command`).
+    let shell = resolve_program(&shell).map_err(|e| AppError::command(e.to_string()))?;
     let mut cmd = CommandBuilder::new(&shell);
 
     cmd.env("TERM", "xterm-256color");
@@ -56,45 +83,42 @@ pub fn pty_create(
         cmd.env("PATH", path);
     }
 
-    let _child = pty_pair
+    let child = pty_pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppError::command(e.to_string()))?;
 
     let mut reader = pty_pair
         .master
         .try_clone_reader()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppError::command(e.to_string()))?;
 
-    let writer = pty_pair
+    let mut writer = pty_pair
         .master
         .take_writer()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppError::command(e.to_string()))?;
 
-    {
-        let mut pg = pty_state.lock().map_err(|e| e.to_string())?;
-        pg.master = Some(pty_pair.master);
-    }
-
-    {
-        let mut wg = writer_state.lock().map_err(|e| e.to_string())?;
-        wg.writer = Some(writer);
-    }
-
-    // Auto-launch claude in the PTY
-    {
-        let mut wg = writer_state.lock().map_err(|e| e.to_string())?;
-        if let Some(writer) = &mut wg.writer {
-            let _ = writer.write_all(b"claude\n");
-            let _ = writer.flush();
-        }
-    }
+    // Auto-launch the configured command (defaults to `claude`).
+    let launch = command.unwrap_or_else(|| "claude".to_string());
+    let _ = writer.write_all(format!("{}\n", launch).as_bytes());
+    let _ = writer.flush();
 
-    // 🔥 Correct UTF-8 streaming read loop
-    std::thread::spawn(move || {
+    // 🔥 Correct UTF-8 streaming read loop, tagged with the session id.
+    let emit_id = session_id.clone();
+    let reader_thread = std::thread::spawn(move || {
         let mut buf = [0u8; 4096];
         let mut pending: Vec<u8> = Vec::new();
 
+        let emit = |data: String| {
+            let _ = app.emit(
+                "pty-output",
+                PtyOutput {
+                    session_id: emit_id.clone(),
+                    data,
+                },
+            );
+        };
+
         loop {
             match reader.read(&mut buf) {
                 Ok(0) | Err(_) => break,
@@ -104,7 +128,7 @@ pub fn pty_create(
                     loop {
                         match std::str::from_utf8(&pending) {
                             Ok(valid_str) => {
-                                let _ = app.emit("pty-output", valid_str.to_string());
+                                emit(valid_str.to_string());
                                 pending.clear();
                                 break;
                             }
@@ -119,7 +143,7 @@ pub fn pty_create(
                                 let valid_part = &pending[..valid_up_to];
 
                                 if let Ok(valid_str) = std::str::from_utf8(valid_part) {
-                                    let _ = app.emit("pty-output", valid_str.to_string());
+                                    emit(valid_str.to_string());
                                 }
 
                                 pending = pending[valid_up_to..].to_vec();
@@ -131,46 +155,87 @@ pub fn pty_create(
         }
     });
 
+    let mut guard = manager.lock()?;
+    guard.sessions.insert(
+        session_id,
+        PtySession {
+            master: pty_pair.master,
+            writer,
+            child,
+            reader_thread: Some(reader_thread),
+        },
+    );
+
     Ok(())
 }
 
-/// Write raw input data (keystrokes) to the PTY.
+/// Write raw input data (keystrokes) to the session's PTY.
 #[tauri::command]
 pub fn pty_write(
-    state: State<'_, Mutex<WriterState>>,
+    manager: State<'_, Mutex<PtyManager>>,
+    session_id: String,
     data: String,
-) -> Result<(), String> {
-    let mut guard = state.lock().map_err(|e| e.to_string())?;
-
-    if let Some(writer) = &mut guard.writer {
-        writer
-            .write_all(data.as_bytes())
-            .map_err(|e| e.to_string())?;
+) -> Result<(), AppError> {
+    let mut guard = manager.lock()?;
 
-        writer.flush().map_err(|e| e.to_string())?;
+    if let Some(session) = guard.sessions.get_mut(&session_id) {
+        session.writer.write_all(data.as_bytes())?;
+        session.writer.flush()?;
     }
 
     Ok(())
 }
 
-/// Resize the PTY to match the frontend terminal dimensions.
+/// Resize the session's PTY to match the frontend terminal dimensions.
 #[tauri::command]
 pub fn pty_resize(
-    state: State<'_, Mutex<PtyState>>,
+    manager: State<'_, Mutex<PtyManager>>,
+    session_id: String,
     rows: u16,
     cols: u16,
-) -> Result<(), String> {
-    let guard = state.lock().map_err(|e| e.to_string())?;
+) -> Result<(), AppError> {
+    let guard = manager.lock()?;
 
-    if let Some(master) = &guard.master {
-        master
+    if let Some(session) = guard.sessions.get(&session_id) {
+        session
+            .master
             .resize(PtySize {
                 rows,
                 cols,
                 pixel_width: 0,
                 pixel_height: 0,
             })
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| AppError::command(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Close a session: kill its child shell (which ends the read loop), drop its
+/// master PTY and join the reader thread. A no-op if the session id is unknown.
+#[tauri::command]
+pub fn pty_close(
+    manager: State<'_, Mutex<PtyManager>>,
+    session_id: String,
+) -> Result<(), AppError> {
+    // Remove the session under the lock, then join its thread without holding
+    // the lock so other sessions stay responsive.
+    let session = {
+        let mut guard = manager.lock()?;
+        guard.sessions.remove(&session_id)
+    };
+
+    if let Some(mut session) = session {
+        // Kill the child first: the cloned reader fd and the child's slave are
+        // what keep the PTY open, so the reader only sees EOF once the child is
+        // gone. Dropping the master alone would leave `reader.read()` — and
+        // thus the join below — blocked forever.
+        let _ = session.child.kill();
+        let _ = session.child.wait();
+        drop(session.master);
+        if let Some(handle) = session.reader_thread.take() {
+            let _ = handle.join();
+        }
     }
 
     Ok(())